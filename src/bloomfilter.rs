@@ -2,37 +2,205 @@ use std::hash::{Hash, Hasher, BuildHasher};
 use std::fmt::{Binary, Formatter, Error};
 use std::collections::hash_map::{DefaultHasher, RandomState};
 
-pub struct BloomFilter<Storage = Vec<u8>, H = DefaultHasher> {
+/// Double hashing from two `u64` seeds is the only bit-indexing strategy this type
+/// supports; there's no way back to the old caller-supplied-`Vec<Hasher>` design. The
+/// seeds are what let [`to_bytes`](#to_bytes)/[`from_bytes`](#from_bytes) round-trip a
+/// filter's hashing exactly (see [`SeededHasher`]) — arbitrary `Hasher` impls can't be
+/// serialized and reconstructed the same way, so reviving them as an option would have
+/// meant no serialization support for filters built that way. If you need per-element
+/// hashers you control directly, [`CountingBloomFilter`] still keeps that design.
+#[derive(Debug)]
+pub struct BloomFilter<Storage = Vec<u8>> {
     data: Storage,
     data_len: usize,
     elmts_added: usize,
-    hashers: Vec<H>
+    k: usize,
+    seed_1: u64,
+    seed_2: u64
+}
+
+/// A `Hasher` seeded by a plain `u64` instead of the hidden random keys `RandomState`
+/// carries, so the exact same hash stream can be reproduced later from a stored seed
+/// (needed to round-trip a filter through [`BloomFilter::to_bytes`]).
+#[derive(Clone, Copy)]
+struct SeededHasher(u64);
+
+impl SeededHasher {
+    fn new(seed: u64) -> Self {
+        // mix the seed in so seed_1/seed_2 of 0 don't produce a degenerate all-zero state
+        SeededHasher(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+}
+
+impl Hasher for SeededHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // FNV-1a, good enough to decorrelate the two hash streams from each other
+        let mut hash = self.0;
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        self.0 = hash;
+    }
+
+    fn finish(&self) -> u64 {
+        // FNV-1a's low bits are only weakly mixed (two inputs differing by a power of
+        // two in the first byte written can land on the same low bits for every seed),
+        // and get_bit_indecies's `% store_len` looks at exactly those low bits. Run the
+        // result through a splitmix64-style avalanche so every output bit depends on the
+        // whole input, not just the seed.
+        let mut h = self.0;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        h ^= h >> 33;
+        h
+    }
+}
+
+fn hash_with_seed<E: Hash>(elmt: &E, seed: u64) -> u64 {
+    let mut hasher = SeededHasher::new(seed);
+    elmt.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// a fresh, effectively-random u64 to seed a new filter's hashers with
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// marks a buffer produced by [`BloomFilter::to_bytes`], so [`BloomFilter::from_bytes`]
+/// can reject data that wasn't: b"BLM1" read as a big-endian u32, so the buffer (which
+/// stores it little-endian) starts with the bytes b"1MLB"
+const WIRE_MAGIC: u32 = 0x424C_4D31;
+
+/// magic (4) + data_len, k, elmts_added, seed_1, seed_2 (5 * u64)
+const WIRE_HEADER_LEN: usize = 4 + 8 * 5;
+
+/// Error returned by [`BloomFilter::from_bytes`] when a buffer can't be a valid filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromBytesError {
+    /// the buffer is shorter than the header, or shorter than the `data_len` it declares
+    TooShort,
+    /// the buffer doesn't start with the [`to_bytes`](BloomFilter::to_bytes) format marker
+    BadMagic
+}
+
+impl std::fmt::Display for FromBytesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            FromBytesError::TooShort => write!(f, "buffer is too short to be a serialized BloomFilter"),
+            FromBytesError::BadMagic => write!(f, "buffer does not start with the BloomFilter format marker")
+        }
+    }
 }
 
+impl std::error::Error for FromBytesError {}
 
-impl BloomFilter<Vec<u8>, DefaultHasher> {
+
+impl BloomFilter<Vec<u8>> {
     /// creates new BloomFilter with `n_bytes` bytes of storage (n * 8 bits)
-    /// and an default storage of type [`Vec<u8>`](std::vec::Vec) and `m_hashers` different Hasher,
-    /// so each bit gets a different position in the storage when [`add`](#add) is called.
+    /// and a default storage of type [`Vec<u8>`](std::vec::Vec), deriving `m_hashers`
+    /// bit positions per element via double hashing (see [`from_initalized`](#from_initalized)).
     pub fn default_with_settings(n_bytes: usize, m_hashers: usize) -> Self {
         let store = vec![0u8; n_bytes];
 
-        let hashers: Vec<DefaultHasher> = (0..m_hashers).map(|_| {
-            RandomState::new().build_hasher()
-        }).collect();
+        BloomFilter::from_initalized(store, m_hashers)
+    }
+
+    /// creates a new BloomFilter sized to hold `expected_items` elements while
+    /// keeping the false-positive rate around `fp_rate` (e.g. `0.01` for 1%).
+    ///
+    /// The number of storage bits `m` and the number of hashers `k` are derived
+    /// from the standard Bloom filter formulas:
+    ///
+    /// ```text
+    /// m = ceil(-(n * ln(p)) / (ln 2)^2)
+    /// k = round((m / n) * ln 2)
+    /// ```
+    ///
+    /// `m` is rounded up to a whole number of bytes and `k` is clamped to at
+    /// least 1, so callers can say "hold 10k items at 1% error" instead of
+    /// hand-tuning bytes and hashers themselves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `expected_items` is 0 or `fp_rate` is not in `(0, 1)`.
+    pub fn for_false_positive_rate(expected_items: usize, fp_rate: f64) -> Self {
+        assert!(expected_items > 0, "expected_items must be > 0");
+        assert!(fp_rate > 0.0 && fp_rate < 1.0, "fp_rate must be in (0, 1)");
+
+        let n = expected_items as f64;
+        let ln2 = std::f64::consts::LN_2;
+
+        let m_bits = (-(n * fp_rate.ln()) / (ln2 * ln2)).ceil().max(8.0) as usize;
+        let k = (((m_bits as f64 / n) * ln2).round() as usize).max(1);
+
+        let n_bytes = m_bits.div_ceil(8);
+
+        Self::default_with_settings(n_bytes, k)
+    }
+
+    /// Reconstructs a filter previously serialized with [`to_bytes`](#to_bytes).
+    ///
+    /// The hasher seeds travel inside `bytes`, so the rebuilt filter hashes elements
+    /// exactly the way the original did, and queries against it stay valid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FromBytesError> {
+        if bytes.len() < WIRE_HEADER_LEN {
+            return Err(FromBytesError::TooShort);
+        }
+
+        let mut magic = [0u8; 4];
+        magic.copy_from_slice(&bytes[0..4]);
+        if u32::from_le_bytes(magic) != WIRE_MAGIC {
+            return Err(FromBytesError::BadMagic);
+        }
 
-        BloomFilter::from_initalized(store, hashers)
+        let read_u64 = |range: std::ops::Range<usize>| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[range]);
+            u64::from_le_bytes(buf)
+        };
+
+        let data_len = read_u64(4..12) as usize;
+        let k = read_u64(12..20) as usize;
+        let elmts_added = read_u64(20..28) as usize;
+        let seed_1 = read_u64(28..36);
+        let seed_2 = read_u64(36..44);
+
+        let data = &bytes[WIRE_HEADER_LEN..];
+        if data.len() < data_len {
+            return Err(FromBytesError::TooShort);
+        }
+
+        Ok(BloomFilter {
+            data: data[..data_len].to_vec(),
+            data_len,
+            elmts_added,
+            k,
+            seed_1,
+            seed_2
+        })
     }
 }
 
-impl <Storage: AsRef<[u8]> + AsMut<[u8]>, H: Hasher + Clone> BloomFilter<Storage, H> {
-    pub fn from_initalized(store: Storage, hashers: Vec<H>) -> Self {
+impl <Storage: AsRef<[u8]> + AsMut<[u8]>> BloomFilter<Storage> {
+    /// Wraps `store` into a BloomFilter that derives `k` bit positions per element
+    /// using double hashing (the "less hashing, same performance" technique of
+    /// Kirsch & Mitzenmacher): a single pass hashes the element with two independently
+    /// seeded hashers into `h1`/`h2`, and the `i`-th bit position is then
+    /// `(h1 + i * h2) % m`. This gives the same asymptotic false-positive rate as
+    /// using `k` independent hashers, but only ever computes two hashes per element.
+    pub fn from_initalized(store: Storage, k: usize) -> Self {
         let len = store.as_ref().len();
         BloomFilter {
             data: store,
             data_len: len,
             elmts_added: 0,
-            hashers
+            k,
+            seed_1: random_seed(),
+            seed_2: random_seed()
         }
     }
 
@@ -44,20 +212,25 @@ impl <Storage: AsRef<[u8]> + AsMut<[u8]>, H: Hasher + Clone> BloomFilter<Storage
         self.data_len
     }
 
-    /// How many hashes get calculated for each call to [`add`](#add) or [`never_occured`](#never_occured).
+    /// How many bit positions get derived for each call to [`add`](#add) or [`never_occured`](#never_occured).
     pub fn num_hashers(&self) -> usize {
-        self.hashers.len()
+        self.k
     }
 
 
-    fn get_bit_indecies<E: Hash>(&self, elmt: & E) -> Vec<usize> {
+    fn get_bit_indecies<E: Hash>(&self, elmt: &E) -> Vec<usize> {
 
         let store_len = self.data_len * 8;
 
-        self.hashers.iter().map(|h| {
-            let mut h: H = (*h).clone();
-            (&*elmt).hash(&mut h);
-            h.finish() as usize % store_len
+        let h1 = hash_with_seed(elmt, self.seed_1);
+        let mut h2 = hash_with_seed(elmt, self.seed_2);
+
+        // force h2 odd: store_len is always a multiple of 8, so an even h2 would only
+        // ever reach every other slot (or a smaller fraction still), clustering collisions
+        h2 |= 1;
+
+        (0..self.k).map(|i| {
+            h1.wrapping_add((i as u64).wrapping_mul(h2)) as usize % store_len
         }).collect()
     }
 
@@ -95,6 +268,177 @@ impl <Storage: AsRef<[u8]> + AsMut<[u8]>, H: Hasher + Clone> BloomFilter<Storage
     pub fn err_probability(&self) -> f64 {
         let fill_ratio = self.data.as_ref().iter().map(|e| e.count_ones()).sum::<u32>() as f64 / (self.data_len * 8) as f64;
 
+        fill_ratio.powi(self.k as i32)
+    }
+
+    /// Serializes this filter to a self-contained byte buffer, so it can be persisted or
+    /// shipped to another process and reloaded with [`BloomFilter::from_bytes`].
+    ///
+    /// The buffer carries a small header (`data_len`, `num_hashers`, `elmts_added` and the
+    /// two hasher seeds) ahead of the raw bitmask, so the returned bytes are everything
+    /// [`from_bytes`](BloomFilter::from_bytes) needs to rebuild hashers that agree with this
+    /// filter's on every query.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data = self.data.as_ref();
+
+        let mut out = Vec::with_capacity(WIRE_HEADER_LEN + data.len());
+        out.extend_from_slice(&WIRE_MAGIC.to_le_bytes());
+        out.extend_from_slice(&(self.data_len as u64).to_le_bytes());
+        out.extend_from_slice(&(self.k as u64).to_le_bytes());
+        out.extend_from_slice(&(self.elmts_added as u64).to_le_bytes());
+        out.extend_from_slice(&self.seed_1.to_le_bytes());
+        out.extend_from_slice(&self.seed_2.to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    /// Merges `other` into `self` bit-for-bit (`self |= other`), as if every element ever
+    /// added to `other` had also been added to `self`; `other` is left unchanged.
+    ///
+    /// Useful for distributed construction: each worker builds a filter over its own
+    /// shard, then the shards are unioned together into one filter covering everything.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` and `other` don't share the same storage size and hasher
+    /// configuration (count and seeds) — without identical hashers a bit position means
+    /// something different in each filter, so merging them would be meaningless.
+    pub fn union<OtherStorage: AsRef<[u8]>>(&mut self, other: &BloomFilter<OtherStorage>) {
+        self.assert_same_config(other);
+
+        for (a, b) in self.data.as_mut().iter_mut().zip(other.data.as_ref().iter()) {
+            *a |= *b;
+        }
+
+        self.elmts_added += other.elmts_added;
+    }
+
+    /// Intersects `other` into `self` bit-for-bit (`self &= other`), approximating the
+    /// intersection of the two underlying sets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` and `other` don't share the same storage size and hasher
+    /// configuration, for the same reason as [`union`](#union).
+    pub fn intersect<OtherStorage: AsRef<[u8]>>(&mut self, other: &BloomFilter<OtherStorage>) {
+        self.assert_same_config(other);
+
+        for (a, b) in self.data.as_mut().iter_mut().zip(other.data.as_ref().iter()) {
+            *a &= *b;
+        }
+    }
+
+    fn assert_same_config<OtherStorage: AsRef<[u8]>>(&self, other: &BloomFilter<OtherStorage>) {
+        assert_eq!(self.data_len, other.data_len, "BloomFilters must share the same storage size to be combined");
+        assert_eq!(self.k, other.k, "BloomFilters must share the same number of hashers to be combined");
+        assert_eq!(self.seed_1, other.seed_1, "BloomFilters must share the same hasher configuration to be combined");
+        assert_eq!(self.seed_2, other.seed_2, "BloomFilters must share the same hasher configuration to be combined");
+    }
+}
+
+/// A Bloom filter variant that supports removing elements again.
+///
+/// Each slot is a small saturating counter (one [`u8`] per slot by default)
+/// instead of a single bit: [`add`](#add) increments every addressed counter
+/// and [`remove`](#remove) decrements them again, so an element can be pushed
+/// and later popped without disturbing the others.
+///
+/// Counters saturate at `u8::MAX` instead of wrapping, so a pathological
+/// amount of collisions can't corrupt the filter by overflowing back to zero.
+///
+/// Unlike [`BloomFilter`], this keeps one full [`Hasher`] per hash function instead of
+/// double hashing from two seeds: [`from_initalized`](#from_initalized) takes caller-supplied
+/// `Hasher`s, so there are no seeds of ours to derive `h1`/`h2` from.
+pub struct CountingBloomFilter<Storage = Vec<u8>, H = DefaultHasher> {
+    data: Storage,
+    data_len: usize,
+    elmts_added: usize,
+    hashers: Vec<H>
+}
+
+impl CountingBloomFilter<Vec<u8>, DefaultHasher> {
+    /// creates a new CountingBloomFilter with `n_counters` one-byte counters
+    /// and `m_hashers` different Hashers, so each counter gets a different
+    /// position in the storage when [`add`](#add) is called.
+    pub fn default_with_settings(n_counters: usize, m_hashers: usize) -> Self {
+        let store = vec![0u8; n_counters];
+
+        let hashers: Vec<DefaultHasher> = (0..m_hashers).map(|_| {
+            RandomState::new().build_hasher()
+        }).collect();
+
+        CountingBloomFilter::from_initalized(store, hashers)
+    }
+}
+
+impl <Storage: AsRef<[u8]> + AsMut<[u8]>, H: Hasher + Clone> CountingBloomFilter<Storage, H> {
+    pub fn from_initalized(store: Storage, hashers: Vec<H>) -> Self {
+        let len = store.as_ref().len();
+        CountingBloomFilter {
+            data: store,
+            data_len: len,
+            elmts_added: 0,
+            hashers
+        }
+    }
+
+    /// How many counters the storage holds.
+    pub fn storage_size(&self) -> usize {
+        self.data_len
+    }
+
+    /// How many hashes get calculated for each call to [`add`](#add), [`remove`](#remove)
+    /// or [`never_occured`](#never_occured).
+    pub fn num_hashers(&self) -> usize {
+        self.hashers.len()
+    }
+
+    fn get_counter_indecies<E: Hash>(&self, elmt: &E) -> Vec<usize> {
+        let store_len = self.data_len;
+
+        self.hashers.iter().map(|h| {
+            let mut h: H = (*h).clone();
+            (&*elmt).hash(&mut h);
+            h.finish() as usize % store_len
+        }).collect()
+    }
+
+    /// If this function returns true, the value was NEVER added to this
+    /// [`CountingBloomFilter`] (or was added and removed an equal number of times).
+    pub fn never_occured<E: Hash>(&self, elmt: &E) -> bool {
+        let store = self.data.as_ref();
+
+        self.get_counter_indecies(elmt).iter().any(|&i| store[i] == 0)
+    }
+
+    /// Add a hashable Element to the CountingBloomFilter, incrementing every
+    /// addressed counter (saturating so it never wraps back to zero).
+    pub fn add<E: Hash>(&mut self, elmt: &E) {
+        self.elmts_added += 1;
+
+        for i in self.get_counter_indecies(elmt) {
+            let counter = &mut self.data.as_mut()[i];
+            *counter = counter.saturating_add(1);
+        }
+    }
+
+    /// Remove a hashable Element from the CountingBloomFilter, decrementing every
+    /// addressed counter (saturating at zero).
+    ///
+    /// Only call this for elements that were actually [`add`](#add)ed before,
+    /// otherwise unrelated elements sharing a counter may start false-negativing.
+    pub fn remove<E: Hash>(&mut self, elmt: &E) {
+        self.elmts_added = self.elmts_added.saturating_sub(1);
+
+        for i in self.get_counter_indecies(elmt) {
+            let counter = &mut self.data.as_mut()[i];
+            *counter = counter.saturating_sub(1);
+        }
+    }
+
+    pub fn err_probability(&self) -> f64 {
+        let fill_ratio = self.data.as_ref().iter().filter(|&&c| c != 0).count() as f64 / self.data_len as f64;
+
         fill_ratio.powi(self.hashers.len() as i32)
     }
 }
@@ -118,7 +462,7 @@ impl Binary for BloomFilter {
 
 #[cfg(test)]
 mod tests {
-    use crate::bloomfilter::BloomFilter;
+    use crate::bloomfilter::{BloomFilter, CountingBloomFilter};
 
     #[test]
     fn filter_test_basic() {
@@ -148,4 +492,88 @@ mod tests {
         println!("{:b}", filter);
         println!("ErrProb.: {}", filter.err_probability());
     }
+
+    #[test]
+    fn filter_for_false_positive_rate() {
+        let mut filter = BloomFilter::for_false_positive_rate(1000, 0.01);
+
+        assert!(filter.storage_size() > 0);
+        assert!(filter.num_hashers() >= 1);
+
+        for i in 0..1000 {
+            filter.add(&i);
+        }
+
+        assert!((0..1000).all(|i| !filter.never_occured(&i)));
+    }
+
+    #[test]
+    fn filter_to_from_bytes_roundtrip() {
+        let mut filter = BloomFilter::default_with_settings(16, 4);
+        filter.add(&2);
+        filter.add(&4);
+
+        let bytes = filter.to_bytes();
+        let restored = BloomFilter::from_bytes(&bytes).expect("valid buffer should decode");
+
+        assert_eq!(restored.storage_size(), filter.storage_size());
+        assert_eq!(restored.num_hashers(), filter.num_hashers());
+        assert!(!restored.never_occured(&2));
+        assert!(!restored.never_occured(&4));
+        assert!(restored.never_occured(&3334));
+    }
+
+    #[test]
+    fn filter_from_bytes_rejects_garbage() {
+        assert_eq!(BloomFilter::from_bytes(&[1, 2, 3]).unwrap_err(), crate::bloomfilter::FromBytesError::TooShort);
+        assert_eq!(BloomFilter::from_bytes(&[0u8; 64]).unwrap_err(), crate::bloomfilter::FromBytesError::BadMagic);
+    }
+
+    #[test]
+    fn filter_union_and_intersect() {
+        let mut a = BloomFilter::default_with_settings(16, 4);
+        a.add(&2);
+
+        // clone a's hasher configuration via from_bytes so union/intersect are valid
+        let mut b = BloomFilter::from_bytes(&a.to_bytes()).unwrap();
+        b.add(&4);
+
+        let mut union = BloomFilter::from_bytes(&a.to_bytes()).unwrap();
+        union.union(&b);
+        assert!(!union.never_occured(&2));
+        assert!(!union.never_occured(&4));
+
+        let mut intersection = BloomFilter::from_bytes(&a.to_bytes()).unwrap();
+        intersection.intersect(&b);
+        assert!(intersection.never_occured(&4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn filter_union_rejects_mismatched_config() {
+        let mut a = BloomFilter::default_with_settings(16, 4);
+        let b = BloomFilter::default_with_settings(16, 4);
+
+        a.union(&b);
+    }
+
+    #[test]
+    fn counting_filter_add_remove() {
+        let mut filter = CountingBloomFilter::default_with_settings(16, 4);
+
+        assert!((0..100).all(|e| filter.never_occured(&e)));
+
+        filter.add(&2);
+        filter.add(&4);
+
+        assert!(!filter.never_occured(&2));
+        assert!(!filter.never_occured(&4));
+
+        filter.remove(&2);
+        assert!(filter.never_occured(&2));
+        assert!(!filter.never_occured(&4));
+
+        filter.remove(&4);
+        assert!(filter.never_occured(&4));
+    }
 }
@@ -0,0 +1,133 @@
+use std::hash::Hash;
+use std::fmt::{Formatter, Error};
+
+use crate::bloomfilter::BloomFilter;
+
+/// Hard cap on cascade depth: `fp_rate` close to 1 barely shrinks the false-positive
+/// set from one level to the next, so a caller picking e.g. `0.95` (an easy mistake —
+/// it reads like "95% accurate") can otherwise make [`BloomCascade::build`] loop for
+/// tens of thousands of levels before it converges. See [`BuildError::TooManyLevels`].
+const MAX_LEVELS: usize = 64;
+
+/// Error returned by [`BloomCascade::build`] when it can't produce a cascade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// `fp_rate` was too close to 1 to converge within [`MAX_LEVELS`] levels; pick a
+    /// smaller `fp_rate` (e.g. well under `0.5`) so each level meaningfully shrinks the
+    /// false-positive set left for the next one.
+    TooManyLevels
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            BuildError::TooManyLevels => write!(f, "cascade did not converge within {} levels; fp_rate is too close to 1", MAX_LEVELS)
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// A cascade of [`BloomFilter`]s that answers exact (zero false-positive) membership
+/// queries for a known set, using far less space than storing the set directly.
+///
+/// A single filter has false positives; each extra level encodes what the previous
+/// level got wrong, flipping which side (members/non-members) it encodes, until a
+/// level has no false positives left to correct.
+#[derive(Debug)]
+pub struct BloomCascade {
+    levels: Vec<BloomFilter>
+}
+
+impl BloomCascade {
+    /// Builds a cascade encoding `set` (a subset of `universe`) so that [`contains`](#contains)
+    /// answers exactly, with each level sized for `fp_rate` false positives.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::TooManyLevels`] if the cascade doesn't converge within
+    /// [`MAX_LEVELS`] levels, which happens when `fp_rate` is too close to 1 for each
+    /// level to meaningfully shrink the false-positive set left for the next one.
+    pub fn build<T: Hash + Eq + Clone>(set: &[T], universe: &[T], fp_rate: f64) -> Result<Self, BuildError> {
+        let mut levels = Vec::new();
+
+        let mut encode: Vec<T> = set.to_vec();
+        let mut other: Vec<T> = universe.iter().filter(|u| !set.contains(u)).cloned().collect();
+
+        loop {
+            if levels.len() >= MAX_LEVELS {
+                return Err(BuildError::TooManyLevels);
+            }
+
+            let mut filter = BloomFilter::for_false_positive_rate(encode.len().max(1), fp_rate);
+            for elmt in &encode {
+                filter.add(elmt);
+            }
+
+            let false_positives: Vec<T> = other.iter()
+                .filter(|o| !filter.never_occured(o))
+                .cloned()
+                .collect();
+
+            levels.push(filter);
+
+            if false_positives.is_empty() {
+                break;
+            }
+
+            // membership flips for the next level: the elements we got wrong become what
+            // we encode, and the elements we got right become the new universe to check against
+            other = encode;
+            encode = false_positives;
+        }
+
+        Ok(BloomCascade { levels })
+    }
+
+    /// How many filter levels this cascade is made of.
+    pub fn num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Exact membership query: unlike a single [`BloomFilter`], this never has false positives.
+    ///
+    /// Each level is queried in turn; membership flips at every level, so the first level
+    /// that reports the element was [`never_occured`](BloomFilter::never_occured) gives the
+    /// answer directly (odd depth means "in `S`"). If every level reports the element as
+    /// possibly present, the last level was built with no false positives left, so the
+    /// element must be a genuine member of whatever it encodes there.
+    pub fn contains<T: Hash>(&self, elmt: &T) -> bool {
+        for (depth, filter) in self.levels.iter().enumerate() {
+            if filter.never_occured(elmt) {
+                return depth % 2 == 1;
+            }
+        }
+
+        self.levels.len() % 2 == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cascade::BloomCascade;
+
+    #[test]
+    fn cascade_exact_membership() {
+        let universe: Vec<usize> = (0..200).collect();
+        let set: Vec<usize> = (0..200).step_by(3).collect();
+
+        let cascade = BloomCascade::build(&set, &universe, 0.3).unwrap();
+
+        for elmt in &universe {
+            assert_eq!(cascade.contains(elmt), set.contains(elmt), "mismatch for {}", elmt);
+        }
+    }
+
+    #[test]
+    fn cascade_rejects_fp_rate_too_close_to_one() {
+        let universe: Vec<usize> = (0..2000).collect();
+        let set: Vec<usize> = (0..2000).step_by(3).collect();
+
+        assert_eq!(BloomCascade::build(&set, &universe, 0.95).unwrap_err(), crate::cascade::BuildError::TooManyLevels);
+    }
+}
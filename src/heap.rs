@@ -26,23 +26,54 @@ impl <T, F: Fn(&T, &T) -> Ordering> MinHeap<T, F> {
         parent * self.n + n + 1
     }
 
-    // TODO no recursion (see wikipedia)
-    fn heapify(&mut self, i: usize) {
-        // TODO assert isheap for all children
-        let mut min = i;
-        for n_child in 0..self.n {
-            let idx_child = self.nth_child(i, n_child);
-            if idx_child < self.data.len() && (self.order_fn)(&self.data[min], &self.data[idx_child]) == Ordering::Less {
-                min = idx_child;
+    fn heapify(&mut self, mut i: usize) {
+        loop {
+            let mut min = i;
+            for n_child in 0..self.n {
+                let idx_child = self.nth_child(i, n_child);
+                // `Greater`, not `Less`: the old recursive version compared the wrong way
+                // and could leave a larger element above a smaller one past the first level.
+                if idx_child < self.data.len() && (self.order_fn)(&self.data[min], &self.data[idx_child]) == Ordering::Greater {
+                    min = idx_child;
+                }
+            }
+
+            if min == i {
+                break;
             }
-        }
 
-        if min != i {
             self.data.swap(i, min);
-            self.heapify(min);
+            i = min;
         }
     }
 
+    /// Builds a heap over `data` in O(n) by sifting down every internal node from the
+    /// last one up to the root, instead of inserting elements one at a time (O(n log n)).
+    pub fn from_vec(data: Vec<T>, ordering: F, num_children: usize) -> Self {
+        let mut heap = MinHeap {
+            data,
+            order_fn: ordering,
+            n: num_children
+        };
+
+        for i in (0..=heap.len() / heap.n).rev() {
+            heap.heapify(i);
+        }
+
+        heap
+    }
+
+    /// Drains the heap into a fully ordered `Vec<T>` by repeatedly extracting the
+    /// minimum, giving a d-ary heapsort. The sorted elements are pushed into a newly
+    /// allocated `Vec`; the heap's own buffer is not reused for the result.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut sorted = Vec::with_capacity(self.len());
+        while let Some(item) = self.extract() {
+            sorted.push(item);
+        }
+        sorted
+    }
+
     pub fn insert(&mut self, item: T) {
         let mut i = self.len();
         self.data.push(item);
@@ -93,7 +124,14 @@ mod tests {
         assert_eq!(heap.extract(), Some(11));
         assert_eq!(heap.extract(), None);
         assert_eq!(heap.len(), 0);
+    }
 
+    #[test]
+    fn heap_from_vec_and_into_sorted_vec() {
+        let data = vec![9, 3, 7, 1, 8, 2, 6, 4, 5, 0];
+        let heap: MinHeap<usize, _> = MinHeap::from_vec(data, |a: &usize, b: &usize| a.cmp(b), 3);
 
+        assert_eq!(heap.len(), 10);
+        assert_eq!(heap.into_sorted_vec(), (0..10).collect::<Vec<_>>());
     }
 }
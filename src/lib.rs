@@ -1,4 +1,5 @@
 pub mod bloomfilter;
+pub mod cascade;
 pub mod heap;
 
 